@@ -1,16 +1,20 @@
-use bevy::prelude::KeyCode;
+use bevy::prelude::{KeyCode, Rect, Vec2};
+use crate::protocol;
+use crate::pattern::{glob_match, url_match};
+use tap::prelude::*;
+use std::sync::Arc;
+use std::path::{Path, PathBuf};
 
 
 /// The configuration for the devtools.
 ///
-/// Default: `DevTools::Debug`
-#[derive(Debug, Clone, Copy, Default)]
+/// Default: `DevTools::Debug(None)`
+#[derive(Debug, Clone, Copy)]
 pub enum DevTools {
-  /// Always allow the devtools, even in release builds.
-  Always,
-  /// Allow the devtools only in debug builds.
-  #[default]
-  Debug,
+  /// Always allow the devtools, even in release builds. (Optionally, toggle when custom key pressed)
+  Always(Option<KeyCode>),
+  /// Allow the devtools only in debug builds. (Optionally, toggle when custom key pressed)
+  Debug(Option<KeyCode>),
   /// Never allow the devtools.
   Never,
 }
@@ -20,14 +24,30 @@ impl DevTools {
   /// Check if the devtools is enabled.
   pub fn is_enabled(&self) -> bool {
     match self {
-      DevTools::Always => true,
-      DevTools::Debug  => cfg!(debug_assertions),
-      DevTools::Never  => false,
+      DevTools::Always(_) => true,
+      DevTools::Debug (_) => cfg!(debug_assertions),
+      DevTools::Never     => false,
+    }
+  }
+
+  /// The key toggling the devtools, or `None` if the devtools is disabled.
+  pub fn hotkey(&self) -> Option<KeyCode> {
+    match self {
+      DevTools::Always(key) => *key,
+      DevTools::Debug (key) => if cfg!(debug_assertions) { *key } else { None },
+      DevTools::Never       => None,
     }
   }
 }
 
 
+impl Default for DevTools {
+  fn default() -> Self {
+    DevTools::Debug(None)
+  }
+}
+
+
 /// The configuration for the context menu.
 ///
 /// Default: `ContextMenu::Debug(None)`
@@ -59,3 +79,302 @@ impl Default for ContextMenu {
     ContextMenu::Debug(None)
   }
 }
+
+
+/// The content source for the webview.
+///
+/// Default: `WebviewSource::Html` with an empty document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebviewSource {
+  /// Navigate to the given URL.
+  Url(String),
+  /// Load the given inline HTML string.
+  Html(String),
+  /// Load a local file. Relative paths are resolved against the current working directory.
+  File(PathBuf),
+  /// Load a file through the `AssetServer`, e.g. `ui/index.html` or `embedded://my_crate/ui.html`. \
+  /// The file is served by the `bevy://` custom protocol, so relative links inside the page are
+  /// resolved against the same asset source.
+  Asset(String),
+}
+
+
+impl WebviewSource {
+  /// Resolve the URL to navigate to, or `None` if the source is inline HTML.
+  pub(crate) fn url(&self) -> Option<String> {
+    match self {
+      WebviewSource::Url  (url ) => Some(url.clone()),
+      WebviewSource::Html (_   ) => None,
+      WebviewSource::File (path) => Some(to_file_url(path)),
+      WebviewSource::Asset(path) => Some(protocol::asset_url(path)),
+    }
+  }
+}
+
+
+impl Default for WebviewSource {
+  fn default() -> Self {
+    WebviewSource::Html("<html><head></head><body></body></html>".to_string())
+  }
+}
+
+
+fn to_file_url(path: &Path) -> String {
+  let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+  let path = path.to_string_lossy().replace('\\', "/");
+
+  // each segment is encoded, so `#`, `?`, `%` and spaces stay part of the path
+  let path = path.split('/').map(percent_encode).collect::<Vec<_>>().join("/");
+
+  if path.starts_with('/') { format!("file://{path}") } else { format!("file:///{path}") }
+}
+
+
+/// Percent-encode a path segment, keeping the unreserved characters and `:` of the drive letter.
+fn percent_encode(segment: &str) -> String {
+  segment.bytes().map(|b| match b {
+    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' |
+    b'-' | b'.' | b'_' | b'~' | b':' => (b as char).to_string(),
+    b                                => format!("%{b:02X}"),
+  }).collect()
+}
+
+
+/// The bounds of the webview within the window, in logical pixels.
+///
+/// Default: `WebviewBounds::Fill`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WebviewBounds {
+  /// Fill the whole window.
+  #[default]
+  Fill,
+  /// Place at the position with the size, in logical pixels.
+  Logical { position: Vec2, size: Vec2 },
+  /// Place at the position with the size, in percentages (`0.0` to `100.0`) of the window size.
+  Percent { position: Vec2, size: Vec2 },
+  /// Place at the anchor of the window with the size, the offset is in logical pixels.
+  Anchored { anchor: WebviewAnchor, offset: Vec2, size: Vec2 },
+}
+
+
+impl WebviewBounds {
+  /// Resolve the bounds into a rectangle in logical pixels, with the origin at the top-left
+  /// corner of the window.
+  pub fn resolve(&self, window_size: Vec2) -> Rect {
+    let (position, size) = match *self {
+      WebviewBounds::Fill                        => (Vec2::ZERO, window_size),
+      WebviewBounds::Logical { position, size }  => (position, size),
+      WebviewBounds::Percent { position, size }  => (position * window_size / 100.0, size * window_size / 100.0),
+      WebviewBounds::Anchored { anchor, offset, size } => {
+        let anchor = anchor.fraction();
+        (window_size * anchor - size * anchor + offset, size)
+      }
+    };
+
+    Rect::from_corners(position, position + size)
+  }
+}
+
+
+/// The anchor point of the window for `WebviewBounds::Anchored`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WebviewAnchor {
+  #[default]
+  TopLeft,
+  TopCenter,
+  TopRight,
+  CenterLeft,
+  Center,
+  CenterRight,
+  BottomLeft,
+  BottomCenter,
+  BottomRight,
+}
+
+
+impl WebviewAnchor {
+  /// The anchor point in fractions of the size, with the origin at the top-left corner.
+  fn fraction(&self) -> Vec2 {
+    match self {
+      WebviewAnchor::TopLeft      => Vec2::new(0.0, 0.0),
+      WebviewAnchor::TopCenter    => Vec2::new(0.5, 0.0),
+      WebviewAnchor::TopRight     => Vec2::new(1.0, 0.0),
+      WebviewAnchor::CenterLeft   => Vec2::new(0.0, 0.5),
+      WebviewAnchor::Center       => Vec2::new(0.5, 0.5),
+      WebviewAnchor::CenterRight  => Vec2::new(1.0, 0.5),
+      WebviewAnchor::BottomLeft   => Vec2::new(0.0, 1.0),
+      WebviewAnchor::BottomCenter => Vec2::new(0.5, 1.0),
+      WebviewAnchor::BottomRight  => Vec2::new(1.0, 1.0),
+    }
+  }
+}
+
+
+/// The policy for the new window requests of the webview, e.g. `window.open` or the links with
+/// `target="_blank"`. The requests are reported as `WebviewNewWindow` regardless of the policy.
+///
+/// Default: `NewWindowPolicy::Deny`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NewWindowPolicy {
+  /// Ignore the request.
+  #[default]
+  Deny,
+  /// Navigate the requesting webview to the URL.
+  SameWebview,
+  /// Spawn a new `Window` entity with a `Webview` navigating to the URL. The new webview inherits
+  /// the configuration of the requesting one.
+  NewWindow,
+}
+
+
+/// The policy deciding which URLs the webview is allowed to navigate to. The blocked navigations
+/// are reported as `WebviewNavigationBlocked`, e.g. for opening them in the system browser.
+///
+/// The webview always allows the pages served by the `bevy://` custom protocol from the default or
+/// a registered asset source, and the `about:` URLs. Other URLs, including `data:`, must be allowed
+/// by the policy.
+///
+/// Default: `NavigationPolicy::AllowAll`
+#[derive(Clone, Default)]
+pub enum NavigationPolicy {
+  /// Allow all the navigations.
+  #[default]
+  AllowAll,
+  /// Allow only the URLs matching any of the glob patterns, where `*` matches any sequence of
+  /// characters and `?` matches a single character, e.g. `https://*.example.com/*` or `file:///*`.
+  /// In the `scheme://host` part, the wildcards never match `/`, `?`, `#`, `\` or `@`.
+  Allow(Vec<String>),
+  /// Allow only the URLs accepted by the callback. The callback is called on the main thread
+  /// during the event loop, so it must not block.
+  Custom(Arc<dyn Fn(&str) -> bool + Send + Sync>),
+}
+
+
+impl NavigationPolicy {
+  /// Create a policy allowing only the URLs matching any of the glob patterns.
+  pub fn allow<S: Into<String>>(patterns: impl IntoIterator<Item = S>) -> Self {
+    NavigationPolicy::Allow(patterns.into_iter().map(Into::into).collect())
+  }
+
+  /// Create a policy allowing only the URLs accepted by the callback.
+  pub fn custom(callback: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+    NavigationPolicy::Custom(Arc::new(callback))
+  }
+
+  /// Check if the navigation to the URL is allowed.
+  pub fn is_allowed(&self, url: &str) -> bool {
+    match self {
+      NavigationPolicy::AllowAll         => true,
+      NavigationPolicy::Allow(patterns)  => patterns.iter().any(|p| url_match(p, url)),
+      NavigationPolicy::Custom(callback) => callback(url),
+    }
+  }
+}
+
+
+impl std::fmt::Debug for NavigationPolicy {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      NavigationPolicy::AllowAll        => f.write_str("AllowAll"),
+      NavigationPolicy::Allow(patterns) => f.debug_tuple("Allow").field(patterns).finish(),
+      NavigationPolicy::Custom(_)       => f.write_str("Custom(..)"),
+    }
+  }
+}
+
+
+/// The permissions of the origins to post the messages and invoke the commands, e.g. to trust the
+/// UI served by `bevy://` while restricting an embedded third-party page. The origins and the names
+/// are matched by glob patterns, where `*` matches any sequence of characters and `?` matches a
+/// single character. Like `NavigationPolicy`, the wildcards in the origins never match `/`, `?`,
+/// `#`, `\` or `@`. The input events of the initialization script are always allowed.
+///
+/// The origin is `<scheme>://<host>[:port]` of the page, e.g. `bevy://localhost` for assets, which
+/// is `http://bevy.localhost` on Windows and Android. The pages without a host, like the inline
+/// `Html` sources (usually `about:blank`, depending on the platform) and the `file://` pages, have
+/// the whole page URL as the origin instead, so match them with e.g. `about:*` or `file://*`.
+///
+/// The rejected messages are reported as `WebviewIpcRejected`, and the rejected invokes are
+/// rejected immediately.
+///
+/// Default: `IpcPermissions::AllowAll`
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use bevy_wui::prelude::*;
+///
+/// let permissions = IpcPermissions::default()
+///   .allow("bevy://*", ["*"])
+///   .allow("http://bevy.localhost", ["*"])
+///   .allow("https://*.example.com", ["score", "get_*"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum IpcPermissions {
+  /// Allow all the origins to post any message.
+  #[default]
+  AllowAll,
+  /// Allow the origins to post the messages matched by any of the rules.
+  Rules(Vec<IpcRule>),
+}
+
+
+/// The rule allowing the origins matching the pattern to post the messages of the names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpcRule {
+  /// The pattern of the origins, e.g. `bevy://*` or `https://*.example.com`.
+  pub origin: String,
+  /// The patterns of the allowed message and command names.
+  pub names: Vec<String>,
+}
+
+
+impl IpcPermissions {
+  /// Add a rule allowing the origins matching the pattern to post the messages of the names.
+  pub fn allow<S: Into<String>>(
+    self,
+    origin: impl Into<String>,
+    names : impl IntoIterator<Item = S>,
+  ) -> Self {
+    let rule = IpcRule {
+      origin: origin.into(),
+      names : names.into_iter().map(Into::into).collect(),
+    };
+
+    match self {
+      IpcPermissions::AllowAll     => IpcPermissions::Rules(vec![rule]),
+      IpcPermissions::Rules(rules) => IpcPermissions::Rules(rules.tap_mut(|r| r.push(rule))),
+    }
+  }
+
+  /// Check if the origin is allowed to post the message or invoke the command of the name.
+  pub fn is_allowed(&self, origin: &str, name: &str) -> bool {
+    match self {
+      IpcPermissions::AllowAll     => true,
+      IpcPermissions::Rules(rules) => rules.iter().any(|r|
+        url_match(&r.origin, origin) && r.names.iter().any(|n| glob_match(n, name))),
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn encodes_file_url_segments() {
+    assert_eq!(percent_encode("ui#1.html"), "ui%231.html");
+    assert_eq!(percent_encode("My Games"), "My%20Games");
+    assert_eq!(percent_encode("a?b%c"), "a%3Fb%25c");
+    assert_eq!(percent_encode("C:"), "C:");
+    assert_eq!(percent_encode("画面.html"), "%E7%94%BB%E9%9D%A2.html");
+  }
+
+  #[test]
+  #[cfg(not(windows))]
+  fn builds_file_url() {
+    let url = to_file_url(Path::new("/My Games/ui#1.html"));
+    assert_eq!(url, "file:///My%20Games/ui%231.html");
+  }
+}
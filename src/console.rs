@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+
+/// The level of a console output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsoleLevel {
+  /// `console.debug`
+  Debug,
+  /// `console.log`
+  Log,
+  /// `console.info`
+  Info,
+  /// `console.warn`
+  Warn,
+  /// `console.error`, uncaught errors and unhandled promise rejections.
+  Error,
+}
+
+
+/// The event carrying the console output or an uncaught error of a webview. The output is also
+/// written into the Bevy log, so it's available even when the devtools is disabled.
+#[derive(Debug, Clone, Event)]
+pub struct WebviewConsole {
+  /// The entity of the webview.
+  pub entity: Entity,
+  /// The level of the output.
+  pub level: ConsoleLevel,
+  /// The formatted message of the output.
+  pub message: String,
+  /// The source file of the output, if known.
+  pub source: Option<String>,
+  /// The line in the source file of the output, if known.
+  pub line: Option<u32>,
+}
+
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ConsolePayload {
+  pub level  : ConsoleLevel,
+  pub message: String,
+  #[serde(default)]
+  pub source : Option<String>,
+  #[serde(default)]
+  pub line   : Option<u32>,
+  #[serde(default)]
+  pub column : Option<u32>,
+}
+
+
+/// Write the console output of the webview into the Bevy log.
+pub(crate) fn log_console(entity: Entity, window: Entity, data: &ConsolePayload) {
+  let location = match (&data.source, data.line, data.column) {
+    (Some(source), Some(line), Some(column)) => format!(" ({source}:{line}:{column})"),
+    (Some(source), Some(line), None        ) => format!(" ({source}:{line})"),
+    (Some(source), None      , _           ) => format!(" ({source})"),
+    (None        , _         , _           ) => String::new(),
+  };
+
+  let message = &data.message;
+
+  match data.level {
+    ConsoleLevel::Debug => debug!("[webview {entity:?} in {window:?}] {message}{location}"),
+    ConsoleLevel::Log   |
+    ConsoleLevel::Info  => info! ("[webview {entity:?} in {window:?}] {message}{location}"),
+    ConsoleLevel::Warn  => warn! ("[webview {entity:?} in {window:?}] {message}{location}"),
+    ConsoleLevel::Error => error!("[webview {entity:?} in {window:?}] {message}{location}"),
+  }
+}
@@ -1,6 +1,8 @@
 use bevy::log::*;
+use bevy::math::Rect;
 use bevy::input::mouse::*;
 use bevy::input::keyboard::*;
+use bevy::input::touch::*;
 
 
 pub(crate) fn to_mouse(mb: u16) -> MouseButton {
@@ -15,6 +17,33 @@ pub(crate) fn to_mouse(mb: u16) -> MouseButton {
 }
 
 
+pub(crate) fn to_scroll_unit(mode: u32) -> MouseScrollUnit {
+  match mode {
+    0 => MouseScrollUnit::Pixel,
+    // bevy has no page unit, treat it as lines
+    _ => MouseScrollUnit::Line,
+  }
+}
+
+
+pub(crate) fn to_wry_rect(rect: Rect) -> wry::Rect {
+  wry::Rect {
+    position: wry::dpi::LogicalPosition::new(rect.min.x, rect.min.y).into(),
+    size    : wry::dpi::LogicalSize    ::new(rect.width(), rect.height()).into(),
+  }
+}
+
+
+pub(crate) fn to_touch_phase(phase: &str) -> TouchPhase {
+  match phase {
+    "pointerdown" => TouchPhase::Started,
+    "pointermove" => TouchPhase::Moved,
+    "pointerup"   => TouchPhase::Ended,
+    _             => TouchPhase::Canceled,
+  }
+}
+
+
 pub(crate)  fn to_key(key: &str) -> Key {
   match key {
     "Escape"             => Key::Escape,
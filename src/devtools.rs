@@ -0,0 +1,98 @@
+use crate::webview::*;
+use bevy::prelude::*;
+
+
+/// The action on the devtools of a webview.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DevtoolsAction {
+  /// Open the devtools.
+  Open,
+  /// Close the devtools.
+  Close,
+  /// Open the devtools if closed, otherwise close it.
+  Toggle,
+}
+
+
+/// The event for opening or closing the devtools of a webview. The event is ignored if the
+/// devtools is not enabled by the `DevTools` configuration.
+///
+/// # Platform-specific
+///
+/// - **Android / iOS**: Unsupported.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use bevy::prelude::*;
+/// use bevy_wui::prelude::*;
+///
+/// fn inspect(mut events: EventWriter<WebviewDevtools>, wv: Query<Entity, Without<DevtoolsOpen>>) {
+///   for entity in &wv {
+///     events.send(WebviewDevtools { entity, action: DevtoolsAction::Open });
+///   }
+/// }
+/// ```
+#[derive(Debug, Clone, Event)]
+pub struct WebviewDevtools {
+  /// The entity of the webview.
+  pub entity: Entity,
+  /// The action on the devtools.
+  pub action: DevtoolsAction,
+}
+
+
+/// The marker component on the webview entities whose devtools is open.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct DevtoolsOpen;
+
+
+pub(crate) fn sys_webview_devtools(
+  mut commands: Commands,
+      webviews: NonSend<Webviews>,
+  mut requests: EventReader<WebviewDevtools>,
+      keyboard: Res<ButtonInput<KeyCode>>,
+      entities: Query<(Entity, &Webview, Has<DevtoolsOpen>)>,
+      windows : Query<&Window>,
+) {
+  // the hotkey toggles the devtools of the webviews in the focused window
+  let hotkeys = entities.iter()
+    .filter(|(_, w, _)| w.devtools.hotkey().is_some_and(|k| keyboard.just_pressed(k)))
+    .filter(|(e, _, _)| webviews.0.get(e).is_some_and(|h|
+      windows.get(h.window).is_ok_and(|w| w.focused)))
+    .map(|(entity, _, _)| WebviewDevtools { entity, action: DevtoolsAction::Toggle });
+
+  let requests = requests.read().cloned().collect::<Vec<_>>();
+
+  for request in hotkeys.chain(requests) {
+    let entity = request.entity;
+
+    let (Some(handle), Ok((_, config, _))) = (webviews.0.get(&entity), entities.get(entity))
+      else { warn!("No webview found for entity {entity:?}"); continue; };
+
+    if !config.devtools.is_enabled() {
+      warn!("Devtools of webview entity {entity:?} is not enabled");
+      continue;
+    }
+
+    let open = match request.action {
+      DevtoolsAction::Open   => true,
+      DevtoolsAction::Close  => false,
+      DevtoolsAction::Toggle => !handle.webview.is_devtools_open(),
+    };
+
+    if open { handle.webview.open_devtools(); } else { handle.webview.close_devtools(); }
+  }
+
+  // the devtools may also be closed by the user, so the state is synced every frame
+  for (entity, _, marked) in &entities {
+    let Some(handle) = webviews.0.get(&entity)
+      else { continue; };
+
+    match (handle.webview.is_devtools_open(), marked) {
+      (true , false) => { commands.entity(entity).insert(DevtoolsOpen); }
+      (false, true ) => { commands.entity(entity).remove::<DevtoolsOpen>(); }
+      _              => {}
+    }
+  }
+}
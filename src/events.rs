@@ -1,40 +1,87 @@
+use crate::console::*;
 use crate::convert::*;
+use crate::invoke::*;
+use crate::message::*;
 use crate::webview::*;
 use bevy::prelude::*;
 use bevy::input::mouse::*;
 use bevy::input::keyboard::*;
+use bevy::input::touch::*;
 use serde::Deserialize;
 use bevy::platform_support::collections::HashSet;
 use bevy::input::ButtonState;
+use bevy::ecs::system::SystemParam;
+use bevy::window::{CursorEntered, CursorLeft, CursorMoved, Ime};
+
+
+/// The writers of the input events forwarded from the webview.
+#[derive(SystemParam)]
+pub(crate) struct InputEvents<'w> {
+  ki: EventWriter<'w, KeyboardInput>,
+  mm: EventWriter<'w, MouseMotion>,
+  mb: EventWriter<'w, MouseButtonInput>,
+  mw: EventWriter<'w, MouseWheel>,
+  cm: EventWriter<'w, CursorMoved>,
+  ce: EventWriter<'w, CursorEntered>,
+  cl: EventWriter<'w, CursorLeft>,
+  im: EventWriter<'w, Ime>,
+  tc: EventWriter<'w, TouchInput>,
+}
+
+
+/// The event names reserved for the input events forwarded by the initialization script.
+pub(crate) const SYSTEM_EVENTS: &[&str] = &[
+  "kd", "ku", "md", "mu", "mm", "mw", "ce", "cl", "is", "iu", "ie", "it", "tc", "hr", "cs", "ca",
+];
+
+/// The event name reserved for the requests sent by `invoke(name, args)`.
+pub(crate) const INVOKE_EVENT: &str = "iv";
 
 
 pub(crate) fn sys_webview_events(
-      webviews: NonSend<Webviews>,
-  mut event_ki: EventWriter<KeyboardInput>,
-  mut event_mm: EventWriter<MouseMotion>,
-  mut event_mb: EventWriter<MouseButtonInput>,
+  mut commands: Commands,
+  mut webviews: NonSendMut<Webviews>,
+      evts_reg: Res<WebviewEventRegistry>,
+      cmds_reg: Res<WebviewCommands>,
+      settings: Res<WebviewInvokeSettings>,
+  mut pendings: ResMut<PendingCommands>,
+  mut event_wm: EventWriter<WebviewMessage>,
+  mut event_wr: EventWriter<WebviewRequest>,
+  mut event_in: InputEvents,
+  mut event_cs: EventWriter<WebviewConsole>,
+  mut event_ca: EventWriter<WebviewConfigApplied>,
+  mut wnd_comp: Query<&mut Window>,
   mut presseds: Local<HashSet<Key>>,
 ) {
-  for (entity, webview) in webviews.0.iter() {
-    for event in webview.o_queue.lock().drain(..) {
+  for (entity, webview) in webviews.0.iter_mut() {
+    let events = std::mem::take(&mut *webview.o_queue.lock());
+    let wnd    = webview.window;
+
+    for event in events {
       let Some((name, data)) = event.split_once('\u{1}')
         else { error!("Invalid event: {event}"); continue; };
 
+      let Some(data) = authenticate(name, data, &webview.uuid)
+        else { warn!("Rejected forged system event {name:?} from webview entity {entity:?}"); continue; };
+
       match name {
         "kd" => {
           let Ok(data) = serde_json::from_str::<KeyboardInputPayload>(&data)
             else { error!("Failed to deserialize event data: {data}"); continue; };
 
+          // the focused element is interactive, the key is owned by the page
+          if webview.hit_test && data.owned { continue; }
+
           let logical_key = to_key    (&data.key );
           let key_code    = to_keycode(&data.code);
 
           let repeat = presseds.contains(&logical_key);
           presseds.insert(logical_key.clone());
 
-          event_ki.send(KeyboardInput {
+          event_in.ki.send(KeyboardInput {
             state: ButtonState::Pressed,
-            text: None,
-            window: *entity,
+            text: data.text.map(Into::into),
+            window: wnd,
             repeat,
             key_code,
             logical_key,
@@ -50,10 +97,10 @@ pub(crate) fn sys_webview_events(
 
           presseds.remove(&logical_key);
 
-          event_ki.send(KeyboardInput {
+          event_in.ki.send(KeyboardInput {
             state: ButtonState::Released,
             text: None,
-            window: *entity,
+            window: wnd,
             repeat: false,
             key_code,
             logical_key,
@@ -63,17 +110,65 @@ pub(crate) fn sys_webview_events(
         "mm" => {
           let Ok(data) = serde_json::from_str::<MouseMotionPayload>(&data)
             else { error!("Failed to deserialize event data: {data}"); continue; };
-          event_mm.send(MouseMotion { delta: Vec2::new(data.rel_x, data.rel_y) });
+
+          let Ok(mut window) = wnd_comp.get_mut(wnd)
+            else { continue; };
+
+          let scale_factor = window.resolution.scale_factor();
+          let page_position = Vec2::new(data.x, data.y) / scale_factor;
+
+          if webview.owns(page_position) { continue; }
+
+          // the page position is relative to the webview, offset it by the webview bounds
+          let physical_position = ((page_position + webview.bounds.min) * scale_factor).as_dvec2();
+
+          event_in.mm.send(MouseMotion { delta: Vec2::new(data.rel_x, data.rel_y) });
+
+          let delta = window.physical_cursor_position()
+            .map(|last| (physical_position.as_vec2() - last) / scale_factor);
+
+          // bypass the change detection, otherwise `bevy_winit` will warp the cursor
+          window.bypass_change_detection().set_physical_cursor_position(Some(physical_position));
+
+          event_in.cm.send(CursorMoved {
+            window  : wnd,
+            position: (physical_position / scale_factor as f64).as_vec2(),
+            delta,
+          });
+        }
+
+        // with partial bounds, the cursor crossing the webview edge stays in the window,
+        // where winit already reports the native cursor events
+        "ce" => {
+          let Ok(window) = wnd_comp.get(wnd)
+            else { continue; };
+
+          if !covers_window(webview, window) { continue; }
+
+          event_in.ce.send(CursorEntered { window: wnd });
+        }
+
+        "cl" => {
+          let Ok(mut window) = wnd_comp.get_mut(wnd)
+            else { continue; };
+
+          if !covers_window(webview, &window) { continue; }
+
+          window.bypass_change_detection().set_physical_cursor_position(None);
+          event_in.cl.send(CursorLeft { window: wnd });
         }
 
         "md" => {
           let Ok(data) = serde_json::from_str::<MouseButtonPayload>(&data)
             else { error!("Failed to deserialize event data: {data}"); continue; };
 
-          event_mb.send(MouseButtonInput {
+          // only the presses are filtered, so the releases never get stuck
+          if webview.owns(Vec2::new(data.x, data.y)) { continue; }
+
+          event_in.mb.send(MouseButtonInput {
             button: to_mouse(data.button),
             state : ButtonState::Pressed,
-            window: *entity,
+            window: wnd,
           });
         }
 
@@ -81,23 +176,170 @@ pub(crate) fn sys_webview_events(
           let Ok(data) = serde_json::from_str::<MouseButtonPayload>(&data)
             else { error!("Failed to deserialize event data: {data}"); continue; };
 
-          event_mb.send(MouseButtonInput {
+          event_in.mb.send(MouseButtonInput {
             button: to_mouse(data.button),
             state : ButtonState::Released,
-            window: *entity,
+            window: wnd,
+          });
+        }
+
+        "mw" => {
+          let Ok(data) = serde_json::from_str::<MouseWheelPayload>(&data)
+            else { error!("Failed to deserialize event data: {data}"); continue; };
+
+          if webview.owns(Vec2::new(data.cx, data.cy)) { continue; }
+
+          // the DOM scrolls down with positive delta, while bevy scrolls up
+          event_in.mw.send(MouseWheel {
+            unit  : to_scroll_unit(data.mode),
+            x     : -data.x,
+            y     : -data.y,
+            window: wnd,
+          });
+        }
+
+        "tc" => {
+          let Ok(data) = serde_json::from_str::<TouchInputPayload>(&data)
+            else { error!("Failed to deserialize event data: {data}"); continue; };
+
+          let phase = to_touch_phase(&data.phase);
+          let owned = webview.owns(Vec2::new(data.x, data.y));
+
+          if owned && matches!(phase, TouchPhase::Started | TouchPhase::Moved) { continue; }
+
+          event_in.tc.send(TouchInput {
+            phase,
+            position: Vec2::new(data.x, data.y) + webview.bounds.min,
+            window  : wnd,
+            force   : Some(ForceTouch::Normalized(data.force)),
+            id      : data.id,
+          });
+        }
+
+        "hr" => {
+          let Ok(data) = serde_json::from_str::<HitRegionsPayload>(&data)
+            else { error!("Failed to deserialize event data: {data}"); continue; };
+
+          webview.regions = data.rects.iter()
+            .map(|[x, y, w, h]| Rect::new(*x, *y, x + w, y + h))
+            .collect();
+        }
+
+        "is" => {
+          event_in.im.send(Ime::Preedit { window: wnd, value: String::new(), cursor: Some((0, 0)) });
+        }
+
+        "iu" => {
+          let Ok(data) = serde_json::from_str::<ImePayload>(&data)
+            else { error!("Failed to deserialize event data: {data}"); continue; };
+
+          // the cursor is placed at the end of the preedit, in bytes
+          let cursor = Some((data.value.len(), data.value.len()));
+          event_in.im.send(Ime::Preedit { window: wnd, value: data.value, cursor });
+        }
+
+        "ie" => {
+          let Ok(data) = serde_json::from_str::<ImePayload>(&data)
+            else { error!("Failed to deserialize event data: {data}"); continue; };
+
+          event_in.im.send(Ime::Preedit { window: wnd, value: String::new(), cursor: None });
+          event_in.im.send(Ime::Commit  { window: wnd, value: data.value });
+        }
+
+        "it" => {
+          let Ok(data) = serde_json::from_str::<ImePayload>(&data)
+            else { error!("Failed to deserialize event data: {data}"); continue; };
+
+          event_in.im.send(Ime::Commit { window: wnd, value: data.value });
+        }
+
+        "cs" => {
+          let Ok(data) = serde_json::from_str::<ConsolePayload>(&data)
+            else { error!("Failed to deserialize event data: {data}"); continue; };
+
+          log_console(*entity, wnd, &data);
+
+          event_cs.send(WebviewConsole {
+            entity : *entity,
+            level  : data.level,
+            message: data.message,
+            source : data.source,
+            line   : data.line,
           });
         }
 
-        _ => { error!("Unknown event: {name}, {data}"); }
+        "ca" => {
+          let Ok(data) = serde_json::from_str::<ConfigAppliedPayload>(&data)
+            else { error!("Failed to deserialize event data: {data}"); continue; };
+
+          event_ca.send(WebviewConfigApplied { entity: *entity, version: data.version });
+        }
+
+        INVOKE_EVENT => {
+          let Ok(data) = serde_json::from_str::<InvokePayload>(&data)
+            else { error!("Failed to deserialize event data: {data}"); continue; };
+
+          let request = WebviewRequest {
+            entity: *entity,
+            id    : data.id,
+            name  : data.name,
+            args  : data.args,
+          };
+
+          // requests to the registered commands are run by `sys_run_webview_commands`
+          if cmds_reg.0.contains_key(&request.name) {
+            pendings.0.push(request);
+          } else if settings.reject_unregistered {
+            commands.send_event(request.reject(format!("No command registered for {:?}", request.name)));
+          } else {
+            event_wr.send(request);
+          }
+        }
+
+        name => match evts_reg.0.get(name) {
+          Some(deserialize) => if let Err(err) = deserialize(&mut commands, *entity, data) {
+            error!("Failed to deserialize event data: {name}, {data}, {err}");
+          }
+
+          None => {
+            event_wm.send(WebviewMessage {
+              entity: *entity,
+              name  : name.to_string(),
+              json  : data.to_string(),
+            });
+          }
+        }
       }
     }
   }
 }
 
 
+/// Strip the secret from the data of the system event, or `None` if the secret is missing or wrong.
+/// The system events carry the secret of the webview, otherwise they are forged by the page. The
+/// other events are returned as is.
+fn authenticate<'a>(name: &str, data: &'a str, secret: &str) -> Option<&'a str> {
+  if !SYSTEM_EVENTS.contains(&name) { return Some(data); }
+
+  match data.split_once('\u{1}') {
+    Some((s, data)) if s == secret => Some(data),
+    _                              => None,
+  }
+}
+
+
+/// Check if the webview covers the whole window.
+fn covers_window(webview: &WebviewHandle, window: &Window) -> bool {
+  let bounds = webview.bounds;
+  bounds.min.cmple(Vec2::ZERO).all() && bounds.max.cmpge(window.size()).all()
+}
+
+
 #[derive(Debug, Deserialize)]
 struct MouseButtonPayload {
   button: u16,
+  x     : f32,
+  y     : f32,
 }
 
 
@@ -105,6 +347,18 @@ struct MouseButtonPayload {
 struct MouseMotionPayload {
   rel_x: f32,
   rel_y: f32,
+  x    : f32,
+  y    : f32,
+}
+
+
+#[derive(Debug, Deserialize)]
+struct MouseWheelPayload {
+  x   : f32,
+  y   : f32,
+  mode: u32,
+  cx  : f32,
+  cy  : f32,
 }
 
 
@@ -112,4 +366,112 @@ struct MouseMotionPayload {
 struct KeyboardInputPayload {
   key : String,
   code: String,
+  #[serde(default)]
+  text: Option<String>,
+  #[serde(default)]
+  owned: bool,
+}
+
+
+#[derive(Debug, Deserialize)]
+struct HitRegionsPayload {
+  rects: Vec<[f32; 4]>,
+}
+
+
+#[derive(Debug, Deserialize)]
+struct TouchInputPayload {
+  phase: String,
+  id   : u64,
+  x    : f32,
+  y    : f32,
+  force: f64,
+}
+
+
+#[derive(Debug, Deserialize)]
+struct ConfigAppliedPayload {
+  version: u64,
+}
+
+
+#[derive(Debug, Deserialize)]
+struct ImePayload {
+  value: String,
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SECRET: &str = "9f1c2a4e-6d7b-4c8a-b3e5-0a1b2c3d4e5f";
+
+  /// Split the raw IPC message the same way as `sys_webview_events`.
+  fn receive(event: &str) -> Option<(&str, &str)> {
+    let (name, data) = event.split_once('\u{1}')?;
+    authenticate(name, data, SECRET).map(|data| (name, data))
+  }
+
+  #[test]
+  fn accepts_system_event_with_secret() {
+    let event = format!("kd\u{1}{SECRET}\u{1}{{\"key\":\"a\",\"code\":\"KeyA\"}}");
+    assert_eq!(receive(&event), Some(("kd", r#"{"key":"a","code":"KeyA"}"#)));
+  }
+
+  #[test]
+  fn rejects_system_event_without_secret() {
+    // what `window.ipc.postMessage("kd\u{1}...")` from a page script sends
+    assert_eq!(receive("kd\u{1}{\"key\":\"a\",\"code\":\"KeyA\"}"), None);
+    assert_eq!(receive("md\u{1}{\"button\":0,\"x\":0,\"y\":0}"), None);
+  }
+
+  #[test]
+  fn rejects_system_event_with_wrong_secret() {
+    let event = "kd\u{1}00000000-0000-0000-0000-000000000000\u{1}{\"key\":\"a\",\"code\":\"KeyA\"}";
+    assert_eq!(receive(event), None);
+
+    // the secret must match as a whole, not as a prefix
+    let event = format!("kd\u{1}{}\u{1}{{}}", &SECRET[..8]);
+    assert_eq!(receive(&event), None);
+  }
+
+  #[test]
+  fn rejects_system_event_posted_by_page() {
+    // even with the name check of `post(name, data)` bypassed, it sends `name\u{1}json` without
+    // the secret, and JSON never contains a raw `\u{1}`, so the payload can't smuggle one either
+    for name in SYSTEM_EVENTS {
+      let data  = serde_json::to_string(&format!("{SECRET}\u{1}{{}}")).unwrap();
+      let event = format!("{name}\u{1}{data}");
+      assert_eq!(receive(&event), None, "{name} was accepted");
+    }
+  }
+
+  #[test]
+  fn forwards_only_trusted_input_events() {
+    // the synthetic events dispatched by the page scripts have `isTrusted` unset, the init script
+    // forwards them with the secret if the listener doesn't check it, so every input listener
+    // must be added with `listen`, which drops the untrusted events
+    let script = include_str!("init.js");
+    assert!(script.contains("window.addEventListener(type, e => e.isTrusted && handler(e), options)"));
+
+    let inputs = [
+      "keydown", "keyup", "mousedown", "mouseup", "mousemove", "mouseover", "mouseout", "wheel",
+      "compositionstart", "compositionupdate", "compositionend", "beforeinput",
+    ];
+
+    for input in inputs {
+      assert!(!script.contains(&format!("addEventListener(\"{input}\"")), "{input} is not checked");
+      assert!( script.contains(&format!("listen(\"{input}\"")), "{input} is not listened");
+    }
+
+    // the pointer events are listened in loops over the event types, only `listen` adds them
+    assert_eq!(script.matches("addEventListener(type,").count(), 1);
+  }
+
+  #[test]
+  fn passes_other_events_through() {
+    assert_eq!(receive("buy\u{1}{\"item\":\"sword\"}"), Some(("buy", r#"{"item":"sword"}"#)));
+    assert_eq!(receive(&format!("{INVOKE_EVENT}\u{1}{{}}")), Some((INVOKE_EVENT, "{}")));
+  }
 }
@@ -0,0 +1,250 @@
+// everything is kept inside the closure, so the page scripts can't read the secret authenticating
+// the system events, nor replace the functions called by bevy
+(() => {
+  const secret       = <<UUID>>;
+  const systemEvents = <<SYSTEM_EVENTS>>;
+
+  // `window.ipc.postMessage` looks up the native function on every call, which the page scripts
+  // could replace, so the native function is captured before any page script runs instead
+  const native      = window.chrome?.webview ?? window.webkit?.messageHandlers?.ipc ?? window.ipc;
+  const postMessage = native.postMessage.bind(native);
+
+  // define a global function of the public API, the page scripts may still declare their own
+  // `post`, `invoke` or `onMessage`, which only shadows the API for the page itself
+  const expose = (name, value) => Object.defineProperty(window, name, {
+    value, writable: true, configurable: true, enumerable: false,
+  });
+
+  // define a global hook called by bevy, which can't be replaced by the page scripts
+  const hook = (name, value) => Object.defineProperty(window, name, {
+    value, writable: false, configurable: false, enumerable: false,
+  });
+
+  // post a system event, authenticated by the secret
+  const __post = (name, data) => postMessage(`${name}\u{1}${secret}\u{1}${JSON.stringify(data)}`);
+
+  // listen to the input events dispatched by the browser only, the synthetic events dispatched by
+  // the page scripts, e.g. `window.dispatchEvent(new KeyboardEvent("keydown"))`, are not trusted
+  const listen = (type, handler, options) =>
+    window.addEventListener(type, e => e.isTrusted && handler(e), options);
+
+  // post a message to bevy, the system events are reserved
+  expose("post", function post(name, data) {
+    if (systemEvents.includes(name)) {
+      console.error("You have no permission to post this event.");
+      return;
+    }
+
+    // if name have `\u{1}` in it, it will be ignored
+    if (name.includes("\u{1}")) {
+      console.error("Event name cannot contain '\\u{1}' character.");
+      return;
+    }
+
+    postMessage(`${name}\u{1}${JSON.stringify(data)}`);
+  });
+
+  let   __config          = { version: 0, ...<<CONFIG>> };
+  const __keyCodePressing = new Set();
+
+  // called by bevy to update the config, the whole config is replaced at once and acknowledged
+  hook("__applyConfig", (uuid, version, config) => {
+    if (uuid !== secret || version <= __config.version) return;
+
+    const hitTest = !__config.hitTest && config.hitTest;
+
+    __config = { ...config, version };
+    __post("ca", { version });
+
+    // the regions are not reported while the hit-test mode is disabled, report them again
+    if (hitTest) { __regionsReported = null; __reportRegions(); }
+  });
+
+  listen("keydown", e => __keyCodePressing.add   (e.code));
+  listen("keyup"  , e => __keyCodePressing.delete(e.code));
+
+  window.addEventListener("contextmenu", e => {
+    const { enabled, key } = __config.contextMenu;
+    const activated = key === null || __keyCodePressing.has(key);
+    (!enabled || !activated) ? e.preventDefault() : __keyCodePressing.clear();
+  });
+
+  listen("keydown"  , e => __post("kd", { key: e.key, code: e.code, text: __keyText(e), owned: __focusOwned() }));
+  listen("keyup"    , e => __post("ku", { key: e.key, code: e.code }));
+  // the touch and pen input also fire the compatibility mouse events, which are skipped
+  let __pointerType = "mouse";
+  for (const type of ["pointerdown", "pointermove", "pointerup"]) {
+    listen(type, e => __pointerType = e.pointerType, { capture: true });
+  }
+
+  const __fromMouse = e => __pointerType === "mouse" && !e.sourceCapabilities?.firesTouchEvents;
+
+  listen("mousedown", e => __fromMouse(e) && __post("md", { button: e.button, x: e.clientX, y: e.clientY }));
+  listen("mouseup"  , e => __fromMouse(e) && __post("mu", { button: e.button, x: e.clientX, y: e.clientY }));
+  listen("mousemove", e => __fromMouse(e) && __post("mm", {
+    rel_x: e.movementX,
+    rel_y: e.movementY,
+    // absolute position in physical pixels
+    x: e.clientX * window.devicePixelRatio,
+    y: e.clientY * window.devicePixelRatio,
+  }));
+  for (const type of ["pointerdown", "pointermove", "pointerup", "pointercancel"]) {
+    listen(type, e => {
+      if (e.pointerType !== "touch" && e.pointerType !== "pen") return;
+      // the hovering pen moves without contact, which is not a touch
+      if (e.type === "pointermove" && e.buttons === 0) return;
+      __post("tc", { phase: e.type, id: e.pointerId, x: e.clientX, y: e.clientY, force: e.pressure });
+    });
+  }
+  listen("mouseover", e => e.relatedTarget === null && __post("ce", {}));
+  listen("mouseout" , e => e.relatedTarget === null && __post("cl", {}));
+  listen("wheel"    , e => {
+    // pixel deltas are in CSS pixels, but bevy expects physical pixels
+    const scale = e.deltaMode === WheelEvent.DOM_DELTA_PIXEL ? window.devicePixelRatio : 1;
+    __post("mw", { x: e.deltaX * scale, y: e.deltaY * scale, mode: e.deltaMode, cx: e.clientX, cy: e.clientY });
+  }, { passive: true });
+
+  const __messageHandlers = new Map();
+
+  // listen to the messages sent from bevy, returns a function to stop listening
+  expose("onMessage", function onMessage(name, handler) {
+    if (!__messageHandlers.has(name)) __messageHandlers.set(name, new Set());
+    __messageHandlers.get(name).add(handler);
+    return () => __messageHandlers.get(name).delete(handler);
+  });
+
+  // called by bevy to deliver the messages
+  hook("__dispatchMessage", (name, data) => {
+    for (const handler of __messageHandlers.get(name) ?? []) {
+      try { handler(data); } catch (e) { console.error(e); }
+    }
+  });
+
+  const __pendingInvokes = new Map();
+  // starts at a random offset per document, so a response to the previous document still queued
+  // in bevy won't settle an invoke of the reloaded one; stays below `Number.MAX_SAFE_INTEGER`
+  let   __invokeCounter  = Math.floor(Math.random() * 2 ** 52);
+
+  // invoke a request handled by bevy, returns a promise settled with the response
+  expose("invoke", function invoke(name, args = null, { timeout = 10000 } = {}) {
+    return new Promise((resolve, reject) => {
+      const id    = ++__invokeCounter;
+      const timer = setTimeout(() => {
+        __pendingInvokes.delete(id);
+        reject(new Error(`Invoke "${name}" timed out after ${timeout}ms, no command or handler answered.`));
+      }, timeout);
+
+      __pendingInvokes.set(id, { resolve, reject, timer });
+      postMessage(`<<INVOKE_EVENT>>\u{1}${JSON.stringify({ id, name, args })}`);
+    });
+  });
+
+  // called by bevy to settle the invoke
+  hook("__settleInvoke", (id, ok, value) => {
+    const pending = __pendingInvokes.get(id);
+    if (!pending) return;
+
+    __pendingInvokes.delete(id);
+    clearTimeout(pending.timer);
+    if (ok) pending.resolve(value);
+    else    pending.reject(typeof value === "string" ? new Error(value) : value);
+  });
+
+  // the text produced by the key, or null if the key is not printable or is composing
+  let __lastKeyText = null;
+
+  function __keyText(e) {
+    const printable = [...e.key].length === 1 && !e.ctrlKey && !e.metaKey;
+    return __lastKeyText = (printable && !e.isComposing) ? e.key : null;
+  }
+
+  listen("compositionstart" , e => __post("is", {}));
+  listen("compositionupdate", e => __post("iu", { value: e.data }));
+  listen("compositionend"   , e => __post("ie", { value: e.data }));
+
+  // text inserted without a printable key, e.g. emoji picker or dictation
+  listen("beforeinput", e => {
+    const inserting = e.inputType === "insertText" || e.inputType === "insertReplacementText";
+
+    if (inserting && !e.isComposing && e.data && e.data !== __lastKeyText)
+      __post("it", { value: e.data });
+
+    __lastKeyText = null;
+  });
+
+  // the interactive regions are reported to bevy for the hit-test mode
+  const __interactiveSelector = "[data-wui-interactive], .wui-interactive";
+  let   __regionsScheduled    = false;
+  let   __regionsReported     = "[]";
+
+  function __focusOwned() {
+    return __config.hitTest && !!document.activeElement?.closest?.(__interactiveSelector);
+  }
+
+  function __reportRegions() {
+    if (__regionsScheduled || !__config.hitTest) return;
+    __regionsScheduled = true;
+
+    requestAnimationFrame(() => {
+      __regionsScheduled = false;
+
+      const rects = [...document.querySelectorAll(__interactiveSelector)]
+        .map   (el => el.getBoundingClientRect())
+        .filter(r  => r.width > 0 && r.height > 0)
+        .map   (r  => [r.left, r.top, r.width, r.height]);
+
+      const reported = JSON.stringify(rects);
+      if (reported === __regionsReported) return;
+
+      __regionsReported = reported;
+      __post("hr", { rects });
+    });
+  }
+
+  new MutationObserver(__reportRegions).observe(document, {
+    subtree: true, childList: true, attributes: true,
+  });
+
+  window.addEventListener("load"  , __reportRegions);
+  window.addEventListener("resize", __reportRegions);
+  window.addEventListener("scroll", __reportRegions, { capture: true, passive: true });
+  window.addEventListener("transitionend", __reportRegions);
+  window.addEventListener("animationend" , __reportRegions);
+
+  // the console output and the uncaught errors are forwarded to the bevy log
+  function __formatConsole(value) {
+    if (typeof value === "string") return value;
+    if (value instanceof Error   ) return value.stack ?? `${value.name}: ${value.message}`;
+    try { return JSON.stringify(value) ?? String(value); } catch { return String(value); }
+  }
+
+  // the location of the caller of the console method, e.g. `at fn (url:1:2)` or `fn@url:1:2`
+  function __callerLocation(stack) {
+    const frame = (stack ?? "").split("\n").map(f => f.trim()).filter(f => f && f !== "Error")[1];
+    const match = frame?.match(/\(?([^\s()@]+):(\d+):(\d+)\)?$/);
+    return match ? { source: match[1], line: +match[2], column: +match[3] } : {};
+  }
+
+  for (const level of ["debug", "log", "info", "warn", "error"]) {
+    const original = console[level].bind(console);
+
+    console[level] = (...args) => {
+      original(...args);
+      const message = args.map(__formatConsole).join(" ");
+      __post("cs", { level, message, ...__callerLocation(new Error().stack) });
+    };
+  }
+
+  window.addEventListener("error", e => __post("cs", {
+    level  : "error",
+    message: e.error instanceof Error ? __formatConsole(e.error) : e.message,
+    source : e.filename || null,
+    line   : e.lineno   || null,
+    column : e.colno    || null,
+  }));
+
+  window.addEventListener("unhandledrejection", e => __post("cs", {
+    level  : "error",
+    message: `Unhandled rejection: ${__formatConsole(e.reason)}`,
+  }));
+})();
@@ -0,0 +1,198 @@
+use crate::webview::*;
+use bevy::prelude::*;
+use bevy::platform_support::collections::HashMap;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+
+/// The event carrying a request sent from the webview with `invoke(name, args)`. The promise
+/// returned by `invoke` is settled when a [`WebviewResponse`] for the request is sent, or
+/// rejected when no response is sent within the timeout (10 seconds by default).
+///
+/// Requests to the commands registered with [`WebviewAppExt::add_webview_command`](crate::prelude::WebviewAppExt::add_webview_command)
+/// are routed to the command systems and won't be sent as this event. With
+/// [`WebviewInvokeSettings::reject_unregistered`], the other requests are rejected right away and
+/// this event is never sent.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use bevy::prelude::*;
+/// use bevy_wui::prelude::*;
+///
+/// fn inventory(
+///   mut requests : EventReader<WebviewRequest>,
+///   mut responses: EventWriter<WebviewResponse>,
+/// ) {
+///   for request in requests.read().filter(|r| r.name == "inventory") {
+///     responses.send(request.resolve(&["sword", "shield"]));
+///   }
+/// }
+/// ```
+///
+/// ```js
+/// const items = await invoke("inventory");
+/// const other = await invoke("inventory", { page: 2 }, { timeout: 1000 });
+/// ```
+#[derive(Debug, Clone, Event)]
+pub struct WebviewRequest {
+  /// The entity of the webview which sent the request.
+  pub entity: Entity,
+  /// The correlation ID of the request.
+  pub id: u64,
+  /// The name of the request.
+  pub name: String,
+  /// The arguments of the request.
+  pub args: serde_json::Value,
+}
+
+
+impl WebviewRequest {
+  /// Deserialize the arguments of the request.
+  pub fn deserialize<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+    T::deserialize(&self.args)
+  }
+
+  /// Create a response resolving the promise with the serialized value.
+  pub fn resolve(&self, value: &impl Serialize) -> WebviewResponse {
+    match serde_json::to_value(value) {
+      Ok (value) => WebviewResponse { entity: self.entity, id: self.id, result: Ok(value) },
+      Err(err  ) => self.reject(format!("Failed to serialize the response: {err}")),
+    }
+  }
+
+  /// Create a response rejecting the promise with the error message.
+  pub fn reject(&self, error: impl Into<String>) -> WebviewResponse {
+    WebviewResponse { entity: self.entity, id: self.id, result: Err(error.into().into()) }
+  }
+}
+
+
+/// The settings of the requests sent from the webview with `invoke(name, args)`.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use bevy::prelude::*;
+/// use bevy_wui::prelude::*;
+///
+/// App::new()
+///   .add_plugins((DefaultPlugins, WuiPlugin))
+///   .insert_resource(WebviewInvokeSettings { reject_unregistered: true })
+///   .run();
+/// ```
+#[derive(Debug, Clone, Default, Resource)]
+pub struct WebviewInvokeSettings {
+  /// Whether the requests to the names not registered as commands are rejected right away. \
+  /// By default, they are sent as [`WebviewRequest`] events, and rejected only when the `invoke`
+  /// timeout is reached if no system answers them.
+  pub reject_unregistered: bool,
+}
+
+
+/// The event for settling the promise of a [`WebviewRequest`].
+#[derive(Debug, Clone, Event)]
+pub struct WebviewResponse {
+  /// The entity of the webview which sent the request.
+  pub entity: Entity,
+  /// The correlation ID of the request.
+  pub id: u64,
+  /// The value to resolve the promise with, or the value to reject it with. \
+  /// Rejecting with a string will reject the promise with an `Error` of the message.
+  pub result: Result<serde_json::Value, serde_json::Value>,
+}
+
+
+pub(crate) fn sys_respond_to_webview(
+      webviews : NonSend<Webviews>,
+  mut responses: EventReader<WebviewResponse>,
+) {
+  for response in responses.read() {
+    let Some(handle) = webviews.0.get(&response.entity)
+      else { warn!("No webview found for entity {:?}", response.entity); continue; };
+
+    let (ok, value) = match &response.result {
+      Ok (value) => (true , value),
+      Err(error) => (false, error),
+    };
+
+    handle.i_queue.lock().push(format!("__settleInvoke({}, {ok}, {value});", response.id));
+  }
+}
+
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct InvokePayload {
+  pub id  : u64,
+  pub name: String,
+  #[serde(default)]
+  pub args: serde_json::Value,
+}
+
+
+type CommandRunner = Box<dyn Fn(&mut World, serde_json::Value) -> Result<serde_json::Value, serde_json::Value> + Send + Sync>;
+
+
+/// Storage for `name -> runner` mapping of the registered webview commands.
+#[derive(Default, Resource)]
+pub(crate) struct WebviewCommands(pub HashMap<String, CommandRunner>);
+
+
+impl WebviewCommands {
+  /// Register the system as the command, the system will be registered into the world as a
+  /// one-shot system.
+  pub fn register<Args, Out, E, M>(
+    world : &mut World,
+    name  : String,
+    system: impl IntoSystem<In<Args>, Result<Out, E>, M> + 'static,
+  )
+    where
+      Args: DeserializeOwned + 'static,
+      Out : Serialize + 'static,
+      E   : Serialize + 'static,
+  {
+    let id = world.register_system(system);
+
+    let runner: CommandRunner = Box::new(move |world, args| {
+      let args = Args::deserialize(args).map_err(|err|
+        serde_json::Value::from(format!("Invalid arguments: {err}")))?;
+
+      match world.run_system_with(id, args) {
+        Ok(Ok (out)) => serde_json::to_value(out).map_err(|err|
+          serde_json::Value::from(format!("Failed to serialize the response: {err}"))),
+        Ok(Err(err)) => Err(serde_json::to_value(err).unwrap_or_else(|err|
+          serde_json::Value::from(format!("Failed to serialize the error: {err}")))),
+        Err(err) => Err(serde_json::Value::from(format!("Failed to run the command: {err}"))),
+      }
+    });
+
+    world
+      .get_resource_or_init::<WebviewCommands>()
+      .0.insert(name, runner);
+  }
+}
+
+
+/// The requests to the registered commands waiting to be run.
+#[derive(Default, Resource)]
+pub(crate) struct PendingCommands(pub Vec<WebviewRequest>);
+
+
+pub(crate) fn sys_run_webview_commands(world: &mut World) {
+  let requests = std::mem::take(&mut world.resource_mut::<PendingCommands>().0);
+  if requests.is_empty() { return; }
+
+  world.resource_scope(|world, commands: Mut<WebviewCommands>| {
+    for request in requests {
+      let Some(runner) = commands.0.get(&request.name)
+        else { continue; };
+
+      let result = runner(world, request.args);
+      if let Err(err) = &result {
+        warn!("Webview command {:?} from entity {:?} failed: {err}", request.name, request.entity);
+      }
+
+      world.send_event(WebviewResponse { entity: request.entity, id: request.id, result });
+    }
+  });
+}
@@ -3,9 +3,17 @@ mod config;
 mod events;
 mod webview;
 mod convert;
+mod invoke;
+mod message;
+mod protocol;
+mod pattern;
+mod lifecycle;
+mod console;
+mod devtools;
 
 use tap::prelude::*;
 use bevy::prelude::*;
+use bevy::ui::update::update_clipping_system;
 
 
 /// The plugin for enabling the webview embedment inside the Bevy window.
@@ -19,9 +27,40 @@ impl Plugin for WuiPlugin {
       .add_systems(PreUpdate, (
         webview::sys_create_webview,
         webview::sys_update_webview,
+        webview::sys_update_webview_bounds,
+        webview::sys_resize_webview,
+        webview::sys_update_webview_visibility,
+        webview::sys_order_webview,
         webview::sys_remove_webview,
         events ::sys_webview_events,
+        lifecycle::sys_lifecycle_events,
+        devtools::sys_webview_devtools,
+        invoke ::sys_run_webview_commands,
       ).chain())
+      .add_systems(PostUpdate, (
+        message::sys_send_to_webview,
+        invoke ::sys_respond_to_webview,
+        webview::sys_flush_webview,
+      ).chain())
+      .add_systems(PostUpdate, webview::sys_follow_webview_node.after(update_clipping_system))
+      .add_event::<webview::WebviewResized>()
+      .add_event::<webview::WebviewConfigApplied>()
+      .add_event::<lifecycle::WebviewPageLoad>()
+      .add_event::<lifecycle::WebviewNavigating>()
+      .add_event::<lifecycle::WebviewNavigationBlocked>()
+      .add_event::<lifecycle::WebviewNewWindow>()
+      .add_event::<lifecycle::WebviewIpcRejected>()
+      .add_event::<lifecycle::WebviewTitleChanged>()
+      .add_event::<console::WebviewConsole>()
+      .add_event::<devtools::WebviewDevtools>()
+      .add_event::<message::SendToWebview>()
+      .add_event::<message::WebviewMessage>()
+      .add_event::<invoke::WebviewRequest>()
+      .add_event::<invoke::WebviewResponse>()
+      .init_resource::<message::WebviewEventRegistry>()
+      .init_resource::<invoke::WebviewCommands>()
+      .init_resource::<invoke::PendingCommands>()
+      .init_resource::<invoke::WebviewInvokeSettings>()
       .insert_non_send_resource(webview::Webviews::default())
   ;}
 }
@@ -30,5 +69,15 @@ impl Plugin for WuiPlugin {
 pub mod prelude {
   pub use crate::WuiPlugin;
   pub use crate::config::*;
-  pub use crate::webview::Webview;
+  pub use crate::webview::{
+    Webview, WebviewConfigApplied, WebviewResized, WebviewNode, WebviewTarget, WebviewZIndex,
+  };
+  pub use crate::message::{SendToWebview, WebviewAppExt, WebviewEvent, WebviewMessage};
+  pub use crate::invoke::{WebviewInvokeSettings, WebviewRequest, WebviewResponse};
+  pub use crate::lifecycle::{
+    PageLoadPhase, WebviewIpcRejected, WebviewNavigating, WebviewNavigationBlocked, WebviewNewWindow,
+    WebviewPageLoad, WebviewTitleChanged,
+  };
+  pub use crate::console::{ConsoleLevel, WebviewConsole};
+  pub use crate::devtools::{DevtoolsAction, DevtoolsOpen, WebviewDevtools};
 }
@@ -0,0 +1,212 @@
+use crate::config::*;
+use crate::webview::*;
+use bevy::prelude::*;
+
+
+/// The events reported by the native webview handlers, waiting to be sent as Bevy events.
+#[derive(Debug, Clone)]
+pub(crate) enum NativeEvent {
+  PageLoad    (String, PageLoadPhase),
+  Navigating  (String),
+  Blocked     (String),
+  NewWindow   (String),
+  IpcRejected (String, String),
+  TitleChanged(String),
+}
+
+
+/// The phase of a page load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PageLoadPhase {
+  /// The content of the page has started loading.
+  Started,
+  /// The content of the page has finished loading, the page is ready to receive messages.
+  Finished,
+}
+
+
+impl From<wry::PageLoadEvent> for PageLoadPhase {
+  fn from(event: wry::PageLoadEvent) -> Self {
+    match event {
+      wry::PageLoadEvent::Started  => PageLoadPhase::Started,
+      wry::PageLoadEvent::Finished => PageLoadPhase::Finished,
+    }
+  }
+}
+
+
+/// The event sent when the page of a webview starts or finishes loading.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use bevy::prelude::*;
+/// use bevy_wui::prelude::*;
+///
+/// fn on_ready(mut loads: EventReader<WebviewPageLoad>, mut events: EventWriter<SendToWebview>) {
+///   for load in loads.read().filter(|l| l.phase == PageLoadPhase::Finished) {
+///     events.send(SendToWebview::new(load.entity, "health", &100));
+///   }
+/// }
+/// ```
+#[derive(Debug, Clone, Event)]
+pub struct WebviewPageLoad {
+  /// The entity of the webview.
+  pub entity: Entity,
+  /// The URL of the page.
+  pub url: String,
+  /// The phase of the page load.
+  pub phase: PageLoadPhase,
+}
+
+
+/// The event sent when a webview starts navigating to a URL.
+#[derive(Debug, Clone, Event)]
+pub struct WebviewNavigating {
+  /// The entity of the webview.
+  pub entity: Entity,
+  /// The URL navigating to.
+  pub url: String,
+}
+
+
+/// The event sent when a navigation of a webview is blocked by its `NavigationPolicy`.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use bevy::prelude::*;
+/// use bevy_wui::prelude::*;
+///
+/// fn open_external(mut blockeds: EventReader<WebviewNavigationBlocked>) {
+///   for blocked in blockeds.read() {
+///     // open the URL in the system browser, e.g. with the `open` crate
+///     info!("Opening external link: {}", blocked.url);
+///   }
+/// }
+/// ```
+#[derive(Debug, Clone, Event)]
+pub struct WebviewNavigationBlocked {
+  /// The entity of the webview.
+  pub entity: Entity,
+  /// The URL blocked from navigating to.
+  pub url: String,
+}
+
+
+/// The event sent when a webview requests a new window, e.g. by `window.open` or the links with
+/// `target="_blank"`. The request is handled by the `NewWindowPolicy` of the webview.
+#[derive(Debug, Clone, Event)]
+pub struct WebviewNewWindow {
+  /// The entity of the webview.
+  pub entity: Entity,
+  /// The URL requested to open.
+  pub url: String,
+  /// The entity of the window spawned for the request, with `NewWindowPolicy::NewWindow`.
+  pub window: Option<Entity>,
+}
+
+
+/// The event sent when a message or an invoke of a webview is rejected by its `IpcPermissions`.
+#[derive(Debug, Clone, Event)]
+pub struct WebviewIpcRejected {
+  /// The entity of the webview.
+  pub entity: Entity,
+  /// The origin of the page posting the message.
+  pub origin: String,
+  /// The name of the rejected message or command.
+  pub name: String,
+}
+
+
+/// The event sent when the document title of a webview is changed.
+#[derive(Debug, Clone, Event)]
+pub struct WebviewTitleChanged {
+  /// The entity of the webview.
+  pub entity: Entity,
+  /// The new document title.
+  pub title: String,
+}
+
+
+pub(crate) fn sys_lifecycle_events(
+  mut commands: Commands,
+      webviews: NonSend<Webviews>,
+  mut event_pl: EventWriter<WebviewPageLoad>,
+  mut event_nv: EventWriter<WebviewNavigating>,
+  mut event_nb: EventWriter<WebviewNavigationBlocked>,
+  mut event_tc: EventWriter<WebviewTitleChanged>,
+  mut event_nw: EventWriter<WebviewNewWindow>,
+  mut event_ir: EventWriter<WebviewIpcRejected>,
+      entities: Query<&Webview>,
+  mut wnd_comp: Query<&mut Window>,
+) {
+  for (entity, handle) in webviews.0.iter() {
+    let events = std::mem::take(&mut *handle.n_queue.lock());
+    let entity = *entity;
+
+    for event in events {
+      match event {
+        NativeEvent::PageLoad(url, phase) => {
+          // the new document starts with the initial config, send the current one again
+          if phase == PageLoadPhase::Finished { handle.send_config(); }
+
+          event_pl.send(WebviewPageLoad { entity, url, phase });
+        }
+
+        NativeEvent::Navigating(url) => {
+          event_nv.send(WebviewNavigating { entity, url });
+        }
+
+        NativeEvent::Blocked(url) => {
+          warn!("Blocked webview entity {entity:?} from navigating to {url}");
+          event_nb.send(WebviewNavigationBlocked { entity, url });
+        }
+
+        NativeEvent::NewWindow(url) => {
+          let Ok(config) = entities.get(entity)
+            else { continue; };
+
+          let window = match config.new_window {
+            NewWindowPolicy::Deny => None,
+
+            NewWindowPolicy::SameWebview => {
+              if let Err(err) = handle.webview.load_url(&url) {
+                error!("Failed to navigate webview for entity {entity:?}: {err:?}");
+              }
+
+              None
+            }
+
+            NewWindowPolicy::NewWindow => {
+              let webview = Webview {
+                source: WebviewSource::Url(url.clone()),
+                bounds: WebviewBounds::Fill,
+                ..config.clone()
+              };
+
+              Some(commands.spawn((Window { title: url.clone(), ..default() }, webview)).id())
+            }
+          };
+
+          event_nw.send(WebviewNewWindow { entity, url, window });
+        }
+
+        NativeEvent::IpcRejected(origin, name) => {
+          warn!("Rejected {name:?} posted by {origin} from webview entity {entity:?}");
+          event_ir.send(WebviewIpcRejected { entity, origin, name });
+        }
+
+        NativeEvent::TitleChanged(title) => {
+          let mirror = entities.get(entity).is_ok_and(|w| w.mirror_title);
+
+          if let (true, Ok(mut window)) = (mirror, wnd_comp.get_mut(handle.window)) {
+            window.title = title.clone();
+          }
+
+          event_tc.send(WebviewTitleChanged { entity, title });
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,221 @@
+use crate::events::{SYSTEM_EVENTS, INVOKE_EVENT};
+use crate::invoke::*;
+use crate::webview::*;
+use bevy::prelude::*;
+use bevy::platform_support::collections::HashMap;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+
+/// The event for sending a named JSON message to the webview. The message will be delivered to
+/// the handlers registered with `onMessage(name, handler)` on the JavaScript side.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use bevy::prelude::*;
+/// use bevy_wui::prelude::*;
+///
+/// fn send_health(mut events: EventWriter<SendToWebview>, wnd: Query<Entity, With<Webview>>) {
+///   for wnd in &wnd {
+///     events.send(SendToWebview::new(wnd, "health", &100));
+///   }
+/// }
+/// ```
+///
+/// ```js
+/// onMessage("health", value => console.log(`health: ${value}`));
+/// ```
+#[derive(Debug, Clone, Event)]
+pub struct SendToWebview {
+  /// The entity of the webview to send the message to.
+  pub entity: Entity,
+  /// The name of the message.
+  pub name: String,
+  /// The payload of the message.
+  pub data: serde_json::Value,
+}
+
+
+impl SendToWebview {
+  /// Create a new message with the serialized data.
+  pub fn new(entity: Entity, name: impl Into<String>, data: &impl Serialize) -> Self {
+    let name = name.into();
+    let data = serde_json::to_value(data).unwrap_or_else(|err| {
+      error!("Failed to serialize message data for {name:?}: {err}");
+      serde_json::Value::Null
+    });
+
+    Self { entity, name, data }
+  }
+}
+
+
+pub(crate) fn sys_send_to_webview(
+      webviews: NonSend<Webviews>,
+  mut messages: EventReader<SendToWebview>,
+) {
+  for message in messages.read() {
+    let Some(handle) = webviews.0.get(&message.entity)
+      else { warn!("No webview found for entity {:?}", message.entity); continue; };
+
+    // the name is serialized to be a valid and escaped JS string literal
+    let name = serde_json::to_string(&message.name).unwrap();
+    handle.i_queue.lock().push(format!("__dispatchMessage({name}, {});", message.data));
+  }
+}
+
+
+/// The event carrying a typed message posted from the webview with `post(name, data)`.
+/// Register the message name with [`WebviewAppExt::add_webview_event`] to receive it.
+#[derive(Debug, Clone, Event)]
+pub struct WebviewEvent<T> {
+  /// The entity of the webview which posted the message.
+  pub entity: Entity,
+  /// The deserialized payload of the message.
+  pub data: T,
+}
+
+
+/// The event carrying a message posted from the webview, whose name is not registered with
+/// [`WebviewAppExt::add_webview_event`].
+#[derive(Debug, Clone, Event)]
+pub struct WebviewMessage {
+  /// The entity of the webview which posted the message.
+  pub entity: Entity,
+  /// The name of the message.
+  pub name: String,
+  /// The raw JSON payload of the message.
+  pub json: String,
+}
+
+
+impl WebviewMessage {
+  /// Deserialize the payload of the message.
+  pub fn deserialize<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+    serde_json::from_str(&self.json)
+  }
+}
+
+
+type EventDeserializer = Box<dyn Fn(&mut Commands, Entity, &str) -> serde_json::Result<()> + Send + Sync>;
+
+
+/// Storage for `name -> deserializer` mapping of the registered webview events.
+#[derive(Default, Resource)]
+pub(crate) struct WebviewEventRegistry(pub HashMap<String, EventDeserializer>);
+
+
+/// Extension trait for registering the webview messages on the `App`.
+pub trait WebviewAppExt {
+  /// Register a message name, so `post(name, data)` from the webview will arrive as a typed
+  /// [`WebviewEvent<T>`] instead of the generic [`WebviewMessage`].
+  ///
+  /// # Example
+  ///
+  /// ```rust, no_run
+  /// use bevy::prelude::*;
+  /// use bevy_wui::prelude::*;
+  /// use serde::Deserialize;
+  ///
+  /// #[derive(Debug, Deserialize)]
+  /// struct Buy { item: String, count: u32 }
+  ///
+  /// fn on_buy(mut events: EventReader<WebviewEvent<Buy>>) {
+  ///   for event in events.read() {
+  ///     info!("{:?} wants to buy {} {}", event.entity, event.data.count, event.data.item);
+  ///   }
+  /// }
+  ///
+  /// App::new()
+  ///   .add_plugins((DefaultPlugins, WuiPlugin))
+  ///   .add_webview_event::<Buy>("buy")
+  ///   .add_systems(Update, on_buy)
+  ///   .run();
+  /// ```
+  fn add_webview_event<T>(&mut self, name: impl Into<String>) -> &mut Self
+    where T: DeserializeOwned + Send + Sync + 'static;
+
+  /// Register a system as a command callable by `invoke(name, args)` from the webview. The system
+  /// takes the deserialized arguments as `In<Args>`, and the returned `Result` will resolve or
+  /// reject the promise. Invalid arguments will reject the promise without running the system.
+  ///
+  /// The names not registered as commands are sent as [`WebviewRequest`] events instead, so they
+  /// are not rejected right away: if no system answers the request, the promise is rejected only
+  /// when the `invoke` timeout (10 seconds by default) is reached. Enable
+  /// [`WebviewInvokeSettings::reject_unregistered`] to reject them immediately instead.
+  ///
+  /// # Example
+  ///
+  /// ```rust, no_run
+  /// use bevy::prelude::*;
+  /// use bevy_wui::prelude::*;
+  /// use serde::Deserialize;
+  ///
+  /// #[derive(Debug, Deserialize)]
+  /// struct BuyItem { item: String }
+  ///
+  /// fn buy_item(In(args): In<BuyItem>) -> Result<u32, String> {
+  ///   match args.item.as_str() {
+  ///     "sword" => Ok(100),
+  ///     item    => Err(format!("{item} is sold out")),
+  ///   }
+  /// }
+  ///
+  /// App::new()
+  ///   .add_plugins((DefaultPlugins, WuiPlugin))
+  ///   .add_webview_command("buy_item", buy_item)
+  ///   .run();
+  /// ```
+  ///
+  /// ```js
+  /// const price = await invoke("buy_item", { item: "sword" });
+  /// ```
+  fn add_webview_command<Args, Out, E, M>(
+    &mut self,
+    name  : impl Into<String>,
+    system: impl IntoSystem<In<Args>, Result<Out, E>, M> + 'static,
+  ) -> &mut Self
+    where
+      Args: DeserializeOwned + 'static,
+      Out : Serialize + 'static,
+      E   : Serialize + 'static;
+}
+
+
+impl WebviewAppExt for App {
+  fn add_webview_event<T>(&mut self, name: impl Into<String>) -> &mut Self
+    where T: DeserializeOwned + Send + Sync + 'static
+  {
+    let name = name.into();
+
+    if SYSTEM_EVENTS.contains(&name.as_str()) || name == INVOKE_EVENT {
+      panic!("Webview event name {name:?} is reserved by the system.");
+    }
+
+    self.add_event::<WebviewEvent<T>>();
+    self.world_mut()
+      .get_resource_or_init::<WebviewEventRegistry>()
+      .0.insert(name, Box::new(|commands, entity, json| {
+        let data = serde_json::from_str::<T>(json)?;
+        commands.send_event(WebviewEvent { entity, data });
+        Ok(())
+      }));
+
+    self
+  }
+
+  fn add_webview_command<Args, Out, E, M>(
+    &mut self,
+    name  : impl Into<String>,
+    system: impl IntoSystem<In<Args>, Result<Out, E>, M> + 'static,
+  ) -> &mut Self
+    where
+      Args: DeserializeOwned + 'static,
+      Out : Serialize + 'static,
+      E   : Serialize + 'static,
+  {
+    WebviewCommands::register(self.world_mut(), name.into(), system);
+    self
+  }
+}
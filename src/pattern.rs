@@ -0,0 +1,108 @@
+/// Match the text against the glob pattern, where `*` matches any sequence of characters
+/// (including `/`) and `?` matches a single character.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+  wildcard_match(pattern, text, |_| true)
+}
+
+
+/// Match the URL against the glob pattern. For the hierarchical URLs, the `scheme://authority` part
+/// is matched separately from the rest, and the wildcards in it never match `/`, `?`, `#`, `\` or
+/// `@`, so `https://*.example.com/*` won't match `https://evil.com/?.example.com/`.
+pub(crate) fn url_match(pattern: &str, url: &str) -> bool {
+  if !pattern.contains("://") { return glob_match(pattern, url); }
+
+  // the pattern is split at `/` only, since `?` is a wildcard in the pattern
+  let (p_authority, p_rest) = split_authority(pattern, &['/']);
+  let (u_authority, u_rest) = split_authority(url, &['/', '?', '#', '\\']);
+
+  wildcard_match(p_authority, u_authority, |c| !matches!(c, '/' | '?' | '#' | '\\' | '@'))
+    && glob_match(p_rest, u_rest)
+}
+
+
+/// Split the URL into the `scheme://authority` part and the rest.
+fn split_authority<'a>(url: &'a str, delimiters: &[char]) -> (&'a str, &'a str) {
+  let Some(start) = url.find("://").map(|i| i + 3)
+    else { return (url, ""); };
+
+  let end = url[start..].find(delimiters).map_or(url.len(), |i| start + i);
+  url.split_at(end)
+}
+
+
+/// Match the text against the pattern, where the wildcards only match the characters accepted by
+/// `wild`.
+fn wildcard_match(pattern: &str, text: &str, wild: impl Fn(char) -> bool) -> bool {
+  let text = text.chars().collect::<Vec<_>>();
+
+  // matched[j]: the pattern so far matches the first `j` characters of the text
+  let mut matched = vec![false; text.len() + 1];
+  matched[0] = true;
+
+  for p in pattern.chars() {
+    let mut next = vec![false; text.len() + 1];
+
+    for j in 0..=text.len() {
+      next[j] = match p {
+        '*' => matched[j] || (j > 0 && next[j-1] && wild(text[j-1])),
+        '?' => j > 0 && matched[j-1] && wild(text[j-1]),
+        c   => j > 0 && matched[j-1] && c == text[j-1],
+      };
+    }
+
+    matched = next;
+  }
+
+  matched[text.len()]
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matches_globs() {
+    assert!( glob_match("*", ""));
+    assert!( glob_match("*", "anything/at?all"));
+    assert!( glob_match("get_*", "get_items"));
+    assert!( glob_match("a?c", "abc"));
+    assert!( glob_match("*a*b*", "xxaxxbxx"));
+    assert!(!glob_match("a?c", "ac"));
+    assert!(!glob_match("get_*", "set_items"));
+    assert!(!glob_match("abc", "abcd"));
+  }
+
+  #[test]
+  fn matches_urls() {
+    assert!( url_match("https://*.example.com/*", "https://ui.example.com/"));
+    assert!( url_match("https://*.example.com/*", "https://a.b.example.com/page?q=1#top"));
+    assert!( url_match("file:///*", "file:///C:/My%20Games/ui.html"));
+    assert!( url_match("about:*", "about:blank"));
+    assert!(!url_match("https://*.example.com/*", "http://ui.example.com/"));
+    assert!(!url_match("https://*.example.com/*", "https://example.com.evil.com/"));
+  }
+
+  #[test]
+  fn rejects_authority_bypass() {
+    let pattern = "https://*.example.com/*";
+
+    // `glob_match` alone lets `*` cross into the path, query and fragment
+    assert!(glob_match(pattern, "https://evil.com/?.example.com/"));
+
+    assert!(!url_match(pattern, "https://evil.com/?.example.com/"));
+    assert!(!url_match(pattern, "https://evil.com?.example.com/"));
+    assert!(!url_match(pattern, "https://evil.com#.example.com/"));
+    assert!(!url_match(pattern, "https://evil.com\\.example.com/"));
+    assert!(!url_match(pattern, "https://evil.com/a.example.com/"));
+    assert!(!url_match(pattern, "https://ui.example.com@evil.com/"));
+  }
+
+  #[test]
+  fn matches_origins() {
+    assert!( url_match("bevy://localhost", "bevy://localhost"));
+    assert!( url_match("https://*.example.com", "https://ads.example.com"));
+    assert!(!url_match("http://bevy.localhost", "http://bevy.evil.com"));
+    assert!(!url_match("https://*.example.com", "https://evil.com"));
+  }
+}
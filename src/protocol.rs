@@ -0,0 +1,334 @@
+use std::borrow::Cow;
+use std::path::{Component, Path};
+
+use bevy::prelude::*;
+use bevy::asset::AssetPath;
+use bevy::asset::AssetServerMode;
+use bevy::asset::io::{AssetSourceId, Reader};
+use bevy::tasks::IoTaskPool;
+use wry::http::{header, Request, Response, StatusCode, Uri};
+
+
+/// The name of the custom protocol serving files from the `AssetServer`.
+pub(crate) const PROTOCOL: &str = "bevy";
+
+/// The host used for the default asset source.
+const DEFAULT_HOST: &str = "localhost";
+
+
+/// Build the URL for the given asset path, e.g. `ui/index.html` or `embedded://my_crate/ui.html`.
+///
+/// The URL has the form `bevy://<source>/<path>` where `<source>` is `localhost` for the default
+/// asset source. On Windows and Android, the custom protocol is served as `http://bevy.<source>/<path>`.
+pub(crate) fn asset_url(path: &str) -> String {
+  let path = AssetPath::parse(path);
+  let host = match path.source() {
+    AssetSourceId::Default    => DEFAULT_HOST,
+    AssetSourceId::Name(name) => &**name,
+  };
+
+  let path = path.path().to_string_lossy().replace('\\', "/");
+
+  if cfg!(any(target_os = "windows", target_os = "android")) {
+    format!("http://{PROTOCOL}.{host}/{path}")
+  } else {
+    format!("{PROTOCOL}://{host}/{path}")
+  }
+}
+
+
+/// Check if the URL is served by the custom protocol, i.e. has exactly the form produced by
+/// [`asset_url`] with the default or a registered asset source as the host.
+pub(crate) fn is_asset_url(assets: &AssetServer, url: &str) -> bool {
+  let Ok(uri) = url.parse::<Uri>()
+    else { return false; };
+
+  let (Some(scheme), Some(host)) = (uri.scheme_str(), uri.host())
+    else { return false; };
+
+  // only the form used on current platform, e.g. `http://bevy.<source>` only on Windows and Android
+  let host = if cfg!(any(target_os = "windows", target_os = "android")) {
+    if scheme == "http" { host.strip_prefix(&format!("{PROTOCOL}.")) } else { None }
+  } else if scheme == PROTOCOL {
+    Some(host)
+  } else {
+    None
+  };
+
+  match host {
+    Some(DEFAULT_HOST) => true,
+    Some(name)         => assets.get_source(AssetSourceId::from(name)).is_ok(),
+    None               => false,
+  }
+}
+
+
+/// Resolve the request of the custom protocol through the `AssetServer`.
+pub(crate) fn handle_request(
+  assets   : &AssetServer,
+  request  : Request<Vec<u8>>,
+  responder: wry::RequestAsyncResponder,
+) {
+  let assets = assets.clone();
+
+  IoTaskPool::get().spawn(async move {
+    let response = match read_asset(&assets, &request).await {
+      Ok(data) => respond_with(&request, data),
+      Err(status) => Response::builder()
+        .status(status)
+        .body(Cow::Borrowed(&[][..]))
+        .unwrap(),
+    };
+
+    responder.respond(response);
+  }).detach();
+}
+
+
+async fn read_asset(assets: &AssetServer, request: &Request<Vec<u8>>) -> Result<Vec<u8>, StatusCode> {
+  let uri  = request.uri();
+  let host = uri.host().unwrap_or(DEFAULT_HOST);
+  let host = host.strip_prefix(&format!("{PROTOCOL}.")).unwrap_or(host);
+  let path = percent_decode(uri.path().trim_start_matches('/'));
+
+  // the decoded path may escape the asset source, e.g. `..%2f` or `%2Fetc%2Fpasswd`
+  if !is_relative_path(&path) {
+    warn!("Rejected asset path requested by webview: {path}");
+    return Err(StatusCode::BAD_REQUEST);
+  }
+
+  let source = match host {
+    DEFAULT_HOST => AssetSourceId::Default,
+    name         => AssetSourceId::from(name),
+  };
+
+  let Ok(source) = assets.get_source(source) else {
+    warn!("Unknown asset source requested by webview: {host}");
+    return Err(StatusCode::NOT_FOUND);
+  };
+
+  let reader = match assets.mode() {
+    AssetServerMode::Unprocessed => source.reader(),
+    AssetServerMode::Processed   => source.processed_reader()
+      .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+  };
+
+  let mut file = reader.read(Path::new(&path)).await.map_err(|err| {
+    warn!("Failed to read asset requested by webview: {path}, {err}");
+    StatusCode::NOT_FOUND
+  })?;
+
+  let mut data = Vec::new();
+  file.read_to_end(&mut data).await.map_err(|err| {
+    error!("Failed to read asset requested by webview: {path}, {err}");
+    StatusCode::INTERNAL_SERVER_ERROR
+  })?;
+
+  Ok(data)
+}
+
+
+fn respond_with(request: &Request<Vec<u8>>, data: Vec<u8>) -> Response<Cow<'static, [u8]>> {
+  let mime  = mime_type(request.uri().path());
+  let total = data.len();
+
+  let builder = Response::builder()
+    .header(header::CONTENT_TYPE , mime)
+    .header(header::ACCEPT_RANGES, "bytes");
+
+  let range = request.headers()
+    .get(header::RANGE)
+    .and_then(|r| r.to_str().ok())
+    .map(|r| parse_range(r, total));
+
+  match range {
+    None => builder
+      .status(StatusCode::OK)
+      .header(header::CONTENT_LENGTH, total)
+      .body(Cow::Owned(data))
+      .unwrap(),
+
+    Some(Some((start, end))) => builder
+      .status(StatusCode::PARTIAL_CONTENT)
+      .header(header::CONTENT_LENGTH, end - start + 1)
+      .header(header::CONTENT_RANGE , format!("bytes {start}-{end}/{total}"))
+      .body(Cow::Owned(data[start..=end].to_vec()))
+      .unwrap(),
+
+    Some(None) => builder
+      .status(StatusCode::RANGE_NOT_SATISFIABLE)
+      .header(header::CONTENT_RANGE, format!("bytes */{total}"))
+      .body(Cow::Borrowed(&[][..]))
+      .unwrap(),
+  }
+}
+
+
+/// Parse the `Range` header into an inclusive byte range. Only a single range is supported.
+fn parse_range(range: &str, total: usize) -> Option<(usize, usize)> {
+  let range = range.strip_prefix("bytes=")?;
+  let range = range.split(',').next()?.trim();
+  let (start, end) = range.split_once('-')?;
+
+  let (start, end) = match (start.parse::<usize>(), end.parse::<usize>()) {
+    (Ok (start), Ok (end)) => (start, end.min(total.checked_sub(1)?)),
+    (Ok (start), Err(_  )) => (start, total.checked_sub(1)?),
+    (Err(_    ), Ok (len)) => (total.checked_sub(len.min(total))?, total.checked_sub(1)?),
+    (Err(_    ), Err(_  )) => return None,
+  };
+
+  (start <= end && end < total).then_some((start, end))
+}
+
+
+/// Check if the path stays within the asset source, i.e. has no root, prefix or `..` component.
+fn is_relative_path(path: &str) -> bool {
+  // the backslash is a separator on Windows only, so it's rejected on all platforms
+  !path.contains('\\') && Path::new(path).components()
+    .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+
+fn percent_decode(path: &str) -> String {
+  let bytes = path.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+
+  while i < bytes.len() {
+    let hex = bytes.get(i+1..i+3)
+      .and_then(|h| std::str::from_utf8(h).ok())
+      .and_then(|h| u8::from_str_radix(h, 16).ok());
+
+    match (bytes[i], hex) {
+      (b'%', Some(byte)) => { decoded.push(byte); i += 3; }
+      (byte, _         ) => { decoded.push(byte); i += 1; }
+    }
+  }
+
+  String::from_utf8_lossy(&decoded).into_owned()
+}
+
+
+fn mime_type(path: &str) -> &'static str {
+  let ext = path.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+
+  match ext.as_deref() {
+    Some("html" | "htm") => "text/html",
+    Some("css"         ) => "text/css",
+    Some("js"  | "mjs" ) => "text/javascript",
+    Some("json" | "map") => "application/json",
+    Some("wasm"        ) => "application/wasm",
+    Some("txt"         ) => "text/plain",
+    Some("xml"         ) => "application/xml",
+    Some("svg"         ) => "image/svg+xml",
+    Some("png"         ) => "image/png",
+    Some("jpg" | "jpeg") => "image/jpeg",
+    Some("gif"         ) => "image/gif",
+    Some("webp"        ) => "image/webp",
+    Some("avif"        ) => "image/avif",
+    Some("ico"         ) => "image/x-icon",
+    Some("ttf"         ) => "font/ttf",
+    Some("otf"         ) => "font/otf",
+    Some("woff"        ) => "font/woff",
+    Some("woff2"       ) => "font/woff2",
+    Some("mp3"         ) => "audio/mpeg",
+    Some("ogg" | "oga" ) => "audio/ogg",
+    Some("wav"         ) => "audio/wav",
+    Some("flac"        ) => "audio/flac",
+    Some("mp4"         ) => "video/mp4",
+    Some("webm"        ) => "video/webm",
+    Some("ogv"         ) => "video/ogg",
+    _                    => "application/octet-stream",
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// The path as `read_asset` resolves it from the URL path.
+  fn decoded(path: &str) -> String {
+    percent_decode(path.trim_start_matches('/'))
+  }
+
+  #[test]
+  fn decodes_percent_encoding() {
+    assert_eq!(decoded("/ui/main%20menu.html"), "ui/main menu.html");
+    assert_eq!(decoded("/%E7%94%BB.png"), "画.png");
+    // the invalid escapes are kept as is
+    assert_eq!(decoded("/100%.txt"), "100%.txt");
+    assert_eq!(decoded("/a%zz.txt"), "a%zz.txt");
+  }
+
+  #[test]
+  fn accepts_paths_within_source() {
+    assert!(is_relative_path(&decoded("/ui/index.html")));
+    assert!(is_relative_path(&decoded("/ui/./index.html")));
+    assert!(is_relative_path(&decoded("/ui/..hidden/a.js")));
+  }
+
+  #[test]
+  fn rejects_paths_escaping_source() {
+    assert!(!is_relative_path(&decoded("/..%2f..%2fsecret")));
+    assert!(!is_relative_path(&decoded("/ui/%2E%2E/%2E%2E/secret")));
+    assert!(!is_relative_path(&decoded("/%2Fetc%2Fpasswd")));
+    assert!(!is_relative_path(&decoded("/..%5C..%5Csecret")));
+    assert!(!is_relative_path(&decoded("/C:%5CWindows%5Cwin.ini")));
+    assert!(!is_relative_path("ui\\index.html"));
+  }
+
+  #[test]
+  fn parses_ranges() {
+    assert_eq!(parse_range("bytes=0-4"  , 10), Some((0, 4)));
+    assert_eq!(parse_range("bytes=4-"   , 10), Some((4, 9)));
+    assert_eq!(parse_range("bytes=-3"   , 10), Some((7, 9)));
+    assert_eq!(parse_range("bytes=-30"  , 10), Some((0, 9)));
+    assert_eq!(parse_range("bytes=5-100", 10), Some((5, 9)));
+    // only the first range is served
+    assert_eq!(parse_range("bytes=0-1, 4-5", 10), Some((0, 1)));
+  }
+
+  #[test]
+  fn rejects_unsatisfiable_ranges() {
+    assert_eq!(parse_range("bytes=10-"  , 10), None);
+    assert_eq!(parse_range("bytes=20-30", 10), None);
+    assert_eq!(parse_range("bytes=5-2"  , 10), None);
+    assert_eq!(parse_range("bytes=-0"   , 10), None);
+    assert_eq!(parse_range("bytes=-"    , 10), None);
+    assert_eq!(parse_range("items=0-4"  , 10), None);
+  }
+
+  #[test]
+  fn handles_empty_file() {
+    assert_eq!(parse_range("bytes=0-", 0), None);
+    assert_eq!(parse_range("bytes=-1", 0), None);
+
+    let request  = Request::builder().uri("bevy://localhost/empty.txt").body(Vec::new()).unwrap();
+    let response = respond_with(&request, Vec::new());
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.body().is_empty());
+
+    let request  = Request::builder()
+      .uri("bevy://localhost/empty.txt")
+      .header(header::RANGE, "bytes=0-")
+      .body(Vec::new())
+      .unwrap();
+    let response = respond_with(&request, Vec::new());
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+  }
+
+  #[test]
+  fn serves_partial_content() {
+    let request  = Request::builder()
+      .uri("bevy://localhost/clip.webm")
+      .header(header::RANGE, "bytes=-3")
+      .body(Vec::new())
+      .unwrap();
+    let response = respond_with(&request, (0..10).collect());
+
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 7-9/10");
+    assert_eq!(&response.body()[..], &[7, 8, 9]);
+  }
+}